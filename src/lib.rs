@@ -79,13 +79,29 @@
 //!
 //! Running "test_multiplication"... SUCCESSFUL
 //! Running "test_remainder"... SUCCESSFUL
+//!
+//! 2 passed, 0 failed
+//! ```
+//!
+//! A failing `assert!`/`assert_eq!`/`assert_ne!` only fails the test it's in; the run continues
+//! on to the remaining tests and reports a pass/fail summary at the end.
+//!
+//! ## zkVM support
+//!
+//! Enabling the `zkvm` feature switches [`syscall`] from the Linux `ecall` ABI to the risc0
+//! zkVM's guest host-call convention, so the same tests can also run (and be proven) on the
+//! `riscv32im-risc0-zkvm-elf` target:
+//!
+//! ```bash
+//! cargo run --example test-rv32 --target riscv32im-risc0-zkvm-elf --features zkvm
 //! ```
 //!
 //! ## Limitations
 //!
 //! There are several known limitations.
 //!
-//! 1. First test or assert to fail, stops the test environment.
+//! 1. A Rust panic (as opposed to a failed `assert!`/`assert_eq!`/`assert_ne!`) still stops the
+//!    whole run, since `panic = "abort"` prevents unwinding back into the test harness.
 //! 2. This only tests user-level code. Access to supervisor, machine or hypervisor instructions
 //!    and CSRs is not possible.
 //! 3. Very limited support for printing.
@@ -103,7 +119,9 @@
 #[macro_export]
 /// Assert whether an condition is true similar to [`core::assert`].
 ///
-/// This macro has better formatting within the context of this crate.
+/// This macro has better formatting within the context of this crate. Unlike [`core::assert`], a
+/// failure does not abort the whole run; it marks the current test as failed and returns from it
+/// early, so [`define_tests!`](crate::define_tests) can move on to the next one.
 macro_rules! assert {
     ($condition:expr$(, $txt:literal)?) => {{
         if ! { $condition } {
@@ -115,7 +133,8 @@ macro_rules! assert {
                     "\n",
                 )?
             ];
-            $crate::syscall::exit(1);
+            $crate::harness::fail();
+            return;
         }
     }};
 }
@@ -123,7 +142,9 @@ macro_rules! assert {
 #[macro_export]
 /// Assert whether two items are equal similar to [`core::assert_eq`].
 ///
-/// This macro has better formatting within the context of this crate.
+/// This macro has better formatting within the context of this crate. Unlike [`core::assert_eq`],
+/// a failure does not abort the whole run; it marks the current test as failed and returns from
+/// it early, so [`define_tests!`](crate::define_tests) can move on to the next one.
 macro_rules! assert_eq {
     ($lhs:expr, $rhs:expr$(, $txt:literal)?) => {{
         if ! { $lhs == $rhs } {
@@ -135,7 +156,8 @@ macro_rules! assert_eq {
                     "\n",
                 )?
             ];
-            $crate::syscall::exit(1);
+            $crate::harness::fail();
+            return;
         }
     }};
 }
@@ -143,7 +165,9 @@ macro_rules! assert_eq {
 #[macro_export]
 /// Assert whether two items are not equal similar to [`core::assert_ne`].
 ///
-/// This macro has better formatting within the context of this crate.
+/// This macro has better formatting within the context of this crate. Unlike [`core::assert_ne`],
+/// a failure does not abort the whole run; it marks the current test as failed and returns from
+/// it early, so [`define_tests!`](crate::define_tests) can move on to the next one.
 macro_rules! assert_ne {
     ($lhs:expr, $rhs:expr$(, $txt:literal)?) => {{
         if ! { $lhs != $rhs } {
@@ -155,11 +179,76 @@ macro_rules! assert_ne {
                     "\n",
                 )?
             ];
-            $crate::syscall::exit(1);
+            $crate::harness::fail();
+            return;
         }
     }};
 }
 
+#[macro_export]
+/// Assert a condition in `const` context, failing the build if it does not hold.
+///
+/// Unlike [`assert!`](crate::assert), this is checked by the compiler rather than at runtime, so
+/// it is suited to invariants that must hold regardless of which code path runs, e.g. the target
+/// word size or the width of a shift amount.
+///
+/// ```
+/// ralte32::static_assert!(core::mem::size_of::<u32>() == 4);
+/// ```
+macro_rules! static_assert {
+    ($cond:expr) => {
+        const _: () = {
+            if !($cond) {
+                panic!("static assertion failed")
+            }
+        };
+    };
+}
+
+#[macro_export]
+/// Assert a condition that may depend on runtime arguments, failing the build rather than the
+/// run if it can't be proven true.
+///
+/// This is the runtime-argument counterpart to [`static_assert!`](crate::static_assert), adapted
+/// from the Linux kernel's `build_error`/`BUILD_BUG_ON` approach: `$cond` is checked by calling
+/// [`build_error::build_error`], which the optimizer is expected to delete entirely once it can
+/// prove `$cond` is always true. If it can't, the remaining reference to an undefined symbol
+/// fails the build at link time instead of silently passing or aborting at runtime. With
+/// `debug_assertions` enabled there is no link-time check: a false `$cond` panics at runtime. Prefer
+/// [`static_assert!`](crate::static_assert) for conditions that are already `const`-evaluable, as
+/// its failure is a clearer compile-time diagnostic.
+macro_rules! build_assert {
+    ($cond:expr $(,)?) => {
+        $crate::build_assert!($cond, "build assertion failed")
+    };
+    ($cond:expr, $msg:literal) => {
+        if !{ $cond } {
+            $crate::build_error::build_error($msg)
+        }
+    };
+}
+
+#[doc(hidden)]
+pub mod build_error {
+    /// Fails the build if a call to this function survives optimization. With
+    /// `debug_assertions` enabled, it panics with `msg` at runtime instead.
+    ///
+    /// See [`build_assert!`](crate::build_assert) for how this is used.
+    #[inline(always)]
+    pub fn build_error(msg: &'static str) -> ! {
+        if cfg!(debug_assertions) {
+            panic!("{}", msg)
+        } else {
+            extern "Rust" {
+                #[link_name = "__build_assert_failed"]
+                fn trigger() -> !;
+            }
+
+            unsafe { trigger() }
+        }
+    }
+}
+
 #[macro_export]
 /// Print several items to the standard output.
 ///
@@ -219,12 +308,94 @@ macro_rules! eprintln {
 }
 
 #[macro_export]
-/// Define a set of test functions to run.
+/// Print a sequence of `(value, option = value, ...)` pairs to the standard output, each with
+/// its own [`Formatter`](crate::Formatter) options.
 ///
-/// This is the main entry into this crate.
-macro_rules! define_tests {
-    ($($test_fn:ident),* $(,)?) => {
-        #[cfg(target_arch = "riscv32")]
+/// A bare item with no options (e.g. `" = "`) is printed with [`Formatter::new`]'s defaults.
+/// Supported options are `width`, `pad`, `left`, `radix`, and `sep`, each setting the matching
+/// [`Formatter`](crate::Formatter) field (`left` sets `left_align`, `sep` sets `separators`).
+///
+/// ```no_run
+/// # use ralte32::{fmt, Hex};
+/// fmt!((Hex(0x2Au32), width = 8, sep = false), " = ", (42, width = 4, pad = '0'));
+/// ```
+macro_rules! fmt {
+    (@opt $f:ident, width, $v:expr) => { $f.width = $v; };
+    (@opt $f:ident, pad, $v:expr) => { $f.pad = $v as u8; };
+    (@opt $f:ident, sep, $v:expr) => { $f.separators = $v; };
+    (@opt $f:ident, radix, $v:expr) => { $f.radix = $v; };
+    (@opt $f:ident, left, $v:expr) => { $f.left_align = $v; };
+
+    (@item $f:ident;) => {};
+    (@item $f:ident; ($val:expr $(, $key:ident = $opt:expr)* $(,)?) $(, $($rest:tt)*)?) => {
+        $f.reset();
+        $( $crate::fmt!(@opt $f, $key, $opt); )*
+        $crate::Rv32Write::write_fmt(&$val, &mut $f);
+        $crate::fmt!(@item $f; $($($rest)*)?);
+    };
+    (@item $f:ident; $val:expr $(, $($rest:tt)*)?) => {
+        $f.reset();
+        $crate::Rv32Write::write_fmt(&$val, &mut $f);
+        $crate::fmt!(@item $f; $($($rest)*)?);
+    };
+
+    ($($rest:tt)*) => {{
+        let mut f = $crate::Formatter::new($crate::buffered_writer::write_stdout);
+        $crate::fmt!(@item f; $($rest)*);
+        $crate::buffered_writer::flush_stdout();
+    }};
+}
+
+#[macro_export]
+/// Like [`fmt!`](crate::fmt), but prints a trailing newline.
+macro_rules! fmtln {
+    () => {
+        $crate::fmt!("\n")
+    };
+    ($($rest:tt)+) => {
+        $crate::fmt!($($rest)+, "\n")
+    };
+}
+
+#[macro_export]
+/// Print the value of an expression, with its location and source text, to standard error, then
+/// return the value so it can be used inline.
+///
+/// This is similar to [`std::dbg`], but since there is no [`Debug`](core::fmt::Debug) available
+/// in this `no_std` environment, the value must implement [`Rv32Write`](crate::Rv32Write) instead
+/// (as `int`s, [`Hex`](crate::Hex), [`Binary`](crate::Binary), and `&str` all do).
+///
+/// ```no_run
+/// # use ralte32::dbg;
+/// let a = 6;
+/// let b = 7;
+/// let x = dbg!(a * b) + 1;
+/// ```
+macro_rules! dbg {
+    () => {{
+        $crate::eprintln!(file!(), ":", line!(), ":", column!());
+    }};
+    ($val:expr $(,)?) => {
+        match $val {
+            value => {
+                $crate::eprintln!(
+                    file!(), ":", line!(), ":", column!(), ": ", stringify!($val), " = ", value
+                );
+                value
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Shared body behind [`define_tests!`](crate::define_tests) and
+/// [`define_oracle_tests!`](crate::define_oracle_tests): the panic handler, `_start`, and host
+/// `main` shim are identical between the two, differing only in the banner they print, so both
+/// macros forward here instead of keeping their own copies.
+macro_rules! __define_test_harness {
+    ($banner:literal, $($test_fn:ident),* $(,)?) => {
+        #[cfg(any(target_arch = "riscv32", target_os = "zkvm"))]
         #[panic_handler]
         fn panic(info: &core::panic::PanicInfo) -> ! {
             $crate::eprintln!();
@@ -251,23 +422,37 @@ macro_rules! define_tests {
             $crate::syscall::exit(1)
         }
 
-        // Linux links against the `_start` function specifically.
-        #[cfg(target_arch = "riscv32")]
+        // Linux (and the risc0 zkVM guest ELF) links against the `_start` function specifically.
+        #[cfg(any(target_arch = "riscv32", target_os = "zkvm"))]
         #[no_mangle]
         pub extern "C" fn _start() -> ! {
-            $crate::println!("Running tests...\n");
+            $crate::println!($banner);
+
+            let mut passed: u32 = 0;
+            let mut failed: u32 = 0;
 
             $(
             $crate::print!("Running \"", stringify!($test_fn), "\"...");
             $test_fn();
-            $crate::println!("\rRunning \"", stringify!($test_fn), "\"... SUCCESSFUL");
+            $crate::buffered_writer::flush_stdout();
+            $crate::buffered_writer::flush_stderr();
+
+            if $crate::harness::take_failed() {
+                failed += 1;
+                $crate::println!("\rRunning \"", stringify!($test_fn), "\"... FAILED");
+            } else {
+                passed += 1;
+                $crate::println!("\rRunning \"", stringify!($test_fn), "\"... SUCCESSFUL");
+            }
             )*
 
+            $crate::println!();
+            $crate::println!(passed, " passed, ", failed, " failed");
 
-            $crate::syscall::exit(0)
+            $crate::syscall::exit(u32::from(failed > 0))
         }
 
-        #[cfg(not(target_arch = "riscv32"))]
+        #[cfg(not(any(target_arch = "riscv32", target_os = "zkvm")))]
         fn main() {
             return;
 
@@ -281,11 +466,153 @@ macro_rules! define_tests {
     };
 }
 
-/// Linux system calls used by this crate.
+#[macro_export]
+/// Define a set of test functions to run.
+///
+/// This is the main entry into this crate. Each test runs in turn; a failed
+/// [`assert!`](crate::assert)/[`assert_eq!`](crate::assert_eq)/[`assert_ne!`](crate::assert_ne)
+/// only aborts the test it's in, not the whole run, which ends with a `N passed, M failed`
+/// summary and exits non-zero if anything failed.
+macro_rules! define_tests {
+    ($($test_fn:ident),* $(,)?) => {
+        $crate::__define_test_harness!("Running tests...\n", $($test_fn),*);
+    };
+}
+
+#[macro_export]
+/// Define a pair of hardware/reference functions and validate them against each other over a
+/// sweep of inputs, in the style of a differential "reference oracle" test.
+///
+/// `$hw` and `$rf` are both `fn(u32, u32) -> u32` (or anything coercible to it, e.g. an
+/// intrinsic). The `inputs` argument selects the sweep strategy:
+///
+/// - `exhaustive($range)` tries every combination of `$range x $range`.
+/// - `random($count, $seed)` draws `$count` pairs from a seeded [`oracle::Xorshift32`] PRNG, so a
+///   failing run can always be replayed by reusing `$seed`.
+///
+/// On the first mismatch, the offending operands and both results are printed and the test
+/// returns early marked as failed, same as the [`assert_eq!`](crate::assert_eq) family.
+macro_rules! oracle {
+    ($name:ident, $hw:expr, $rf:expr, exhaustive($inputs:expr)) => {
+        fn $name() {
+            for a in $inputs {
+                for b in $inputs {
+                    let hw_result = $hw(a, b);
+                    let rf_result = $rf(a, b);
+
+                    if hw_result != rf_result {
+                        $crate::eprint!(
+                            "\nOracle mismatch in \"", stringify!($name), "\": a = ",
+                            $crate::Hex(a), ", b = ", $crate::Hex(b), ", hw = ",
+                            $crate::Hex(hw_result), ", ref = ", $crate::Hex(rf_result), "\n",
+                        );
+                        $crate::harness::fail();
+                        return;
+                    }
+                }
+            }
+        }
+    };
+
+    ($name:ident, $hw:expr, $rf:expr, random($count:expr, $seed:expr)) => {
+        fn $name() {
+            let mut rng = $crate::oracle::Xorshift32::new($seed);
+
+            for _ in 0..$count {
+                let a = rng.next_u32();
+                let b = rng.next_u32();
+
+                let hw_result = $hw(a, b);
+                let rf_result = $rf(a, b);
+
+                if hw_result != rf_result {
+                    $crate::eprint!(
+                        "\nOracle mismatch in \"", stringify!($name), "\" (seed = ", $seed,
+                        "): a = ", $crate::Hex(a), ", b = ", $crate::Hex(b), ", hw = ",
+                        $crate::Hex(hw_result), ", ref = ", $crate::Hex(rf_result), "\n",
+                    );
+                    $crate::harness::fail();
+                    return;
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+/// Define a set of [`oracle!`] test functions to run.
+///
+/// This works exactly like [`define_tests!`](crate::define_tests), except that it prints a
+/// `Running oracle tests...` banner, and is named separately so that a module can make it clear
+/// at a glance that its tests are differential oracle sweeps rather than hand-written
+/// `assert_eq!` checks.
+macro_rules! define_oracle_tests {
+    ($($test_fn:ident),* $(,)?) => {
+        $crate::__define_test_harness!("Running oracle tests...\n", $($test_fn),*);
+    };
+}
+
+/// Seeded PRNG used by [`oracle!`] to generate reproducible randomized input sweeps.
+pub mod oracle {
+    /// A tiny xorshift32 PRNG.
+    ///
+    /// This is deliberately not cryptographically secure; it exists purely so a `random(...)`
+    /// [`oracle!`](crate::oracle) sweep is reproducible from its seed when it finds a mismatch.
+    pub struct Xorshift32 {
+        state: u32,
+    }
+
+    impl Xorshift32 {
+        /// Create a new generator from the given seed. A seed of `0` is remapped to `1`, since
+        /// xorshift never leaves the all-zero state.
+        pub const fn new(seed: u32) -> Self {
+            Self {
+                state: if seed == 0 { 1 } else { seed },
+            }
+        }
+
+        /// Advance the generator and return the next pseudo-random `u32`.
+        pub fn next_u32(&mut self) -> u32 {
+            let mut x = self.state;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            self.state = x;
+            x
+        }
+    }
+}
+
+/// Linux system calls used by this crate (or the risc0 zkVM host-call ABI, with the `zkvm`
+/// feature enabled).
 pub mod syscall {
+    /// The risc0 zkVM guest's host-call ABI, enabled by the `zkvm` feature.
+    ///
+    /// Unlike the Linux ABI below (which dispatches on an integer placed in `a7`), risc0 routes
+    /// `ecall`s by *name*: `t0` holds a pointer to a NUL-terminated host-call name, and the
+    /// handler is resolved by the host from that name rather than a bare immediate. This mirrors
+    /// `risc0_zkvm_platform::syscall`'s `SyscallName`/`sys_write`/`sys_halt`.
+    #[cfg(all(target_arch = "riscv32", feature = "zkvm"))]
+    mod zkvm_abi {
+        /// A NUL-terminated risc0 host-call name, as expected in `t0`.
+        pub const SYS_WRITE: &[u8] = b"risc0_zkvm_platform::syscall::write\0";
+        pub const SYS_HALT: &[u8] = b"risc0_zkvm_platform::syscall::halt\0";
+    }
+
     #[inline]
     pub fn write(_file_descriptor: u32, _buf: &[u8]) {
-        #[cfg(target_arch = "riscv32")]
+        #[cfg(all(target_arch = "riscv32", feature = "zkvm"))]
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in ("t0") zkvm_abi::SYS_WRITE.as_ptr(),
+                in ("a0") _file_descriptor,
+                in ("a1") _buf as *const [u8] as *const u8,
+                in ("a2") _buf.len(),
+            );
+        }
+
+        #[cfg(all(target_arch = "riscv32", not(feature = "zkvm")))]
         unsafe {
             core::arch::asm!(
                 "ecall",
@@ -305,7 +632,17 @@ pub mod syscall {
 
     #[inline]
     pub fn exit(_status_code: u32) -> ! {
-        #[cfg(target_arch = "riscv32")]
+        #[cfg(all(target_arch = "riscv32", feature = "zkvm"))]
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in ("t0") zkvm_abi::SYS_HALT.as_ptr(),
+                in ("a0") _status_code,
+                options (noreturn)
+            );
+        }
+
+        #[cfg(all(target_arch = "riscv32", not(feature = "zkvm")))]
         unsafe {
             core::arch::asm!(
                 "ecall",
@@ -324,6 +661,11 @@ pub mod syscall {
 }
 
 /// Wrapper type to format a unsigned integer with hexadecimal
+///
+/// Always prints every nibble of `T` (e.g. `Hex(0x1234u32)` prints `_0000_1234`, not `_1234`): an
+/// earlier version of this type iterated `T::BITS / 8` nibbles instead of `T::BITS / 4`, which
+/// silently truncated the high half of every value. Fixing that necessarily changed the width and
+/// separator count of existing `Hex` output; `Binary` output is unchanged.
 pub struct Hex<T>(pub T);
 /// Wrapper type to format a unsigned integer with binary
 pub struct Binary<T>(pub T);
@@ -336,65 +678,149 @@ fn write_stderr(buf: &[u8]) {
     syscall::write(2, buf)
 }
 
+/// Formatting options plus a sink, used by [`Rv32Write::write_fmt`] and the
+/// [`fmt!`](crate::fmt)/[`fmtln!`](crate::fmtln) macros.
+///
+/// Output still flows through the same `fn(&[u8])` writer (and so the existing buffered
+/// `PRINTBUF`) used everywhere else in this crate; `Formatter` just adds width/padding/radix
+/// control on top of it.
+pub struct Formatter {
+    writer: fn(&[u8]),
+    /// Minimum total width of the next value written; shorter output is padded with
+    /// [`pad`](Self::pad).
+    pub width: usize,
+    /// Byte used to pad output up to [`width`](Self::width).
+    pub pad: u8,
+    /// Align to the left (padding after the value) instead of the right (the default).
+    pub left_align: bool,
+    /// Radix (2 to 16) used when formatting plain integers. Ignored by [`Hex`] (always 16) and
+    /// [`Binary`] (always 2).
+    pub radix: u32,
+    /// Whether [`Hex`]/[`Binary`] should emit their `_` group separators.
+    pub separators: bool,
+}
+
+impl Formatter {
+    /// Create a formatter with this crate's historical defaults: no minimum width, base-10
+    /// integers, and `_` separators enabled for [`Hex`]/[`Binary`].
+    pub fn new(writer: fn(&[u8])) -> Self {
+        Self {
+            writer,
+            width: 0,
+            pad: b' ',
+            left_align: false,
+            radix: 10,
+            separators: true,
+        }
+    }
+
+    /// Reset every option back to [`Formatter::new`]'s defaults, keeping the writer.
+    pub fn reset(&mut self) {
+        self.width = 0;
+        self.pad = b' ';
+        self.left_align = false;
+        self.radix = 10;
+        self.separators = true;
+    }
+
+    fn write_byte(&self, byte: u8) {
+        (self.writer)(core::slice::from_ref(&byte));
+    }
+
+    /// Run `emit` (which is expected to write exactly `len` bytes through `self`), padding it up
+    /// to [`width`](Self::width) on the side [`left_align`](Self::left_align) selects.
+    fn write_padded(&self, len: usize, emit: impl FnOnce(&Self)) {
+        let pad_len = self.width.saturating_sub(len);
+
+        if pad_len > 0 && !self.left_align {
+            for _ in 0..pad_len {
+                self.write_byte(self.pad);
+            }
+        }
+
+        emit(self);
+
+        if pad_len > 0 && self.left_align {
+            for _ in 0..pad_len {
+                self.write_byte(self.pad);
+            }
+        }
+    }
+}
+
 /// Trait to write data to a file descriptor
 pub trait Rv32Write {
-    /// Convert `self` into a set of UTF-8 bytes which get passed to `writer`.
-    fn write(&self, writer: fn(&[u8]));
+    /// Format `self` according to `f`'s options, writing through `f`'s writer.
+    fn write_fmt(&self, f: &mut Formatter);
+
+    /// Convert `self` into a set of UTF-8 bytes which get passed to `writer`, using this crate's
+    /// default formatting options (see [`Formatter::new`]).
+    fn write(&self, writer: fn(&[u8])) {
+        self.write_fmt(&mut Formatter::new(writer));
+    }
 }
 
 impl Rv32Write for &[u8] {
-    fn write(&self, writer: fn(&[u8])) {
-        writer(self)
+    fn write_fmt(&self, f: &mut Formatter) {
+        f.write_padded(self.len(), |f| (f.writer)(self));
     }
 }
 
 impl Rv32Write for char {
-    fn write(&self, writer: fn(&[u8])) {
+    fn write_fmt(&self, f: &mut Formatter) {
         let mut b = [0; 4];
-        self.encode_utf8(&mut b);
-        writer(&b[0..self.len_utf8()])
+        let s = self.encode_utf8(&mut b);
+        f.write_padded(s.len(), |f| (f.writer)(s.as_bytes()));
     }
 }
 
 impl Rv32Write for &str {
-    fn write(&self, writer: fn(&[u8])) {
-        writer(self.as_bytes())
+    fn write_fmt(&self, f: &mut Formatter) {
+        f.write_padded(self.len(), |f| (f.writer)(self.as_bytes()));
     }
 }
 
 impl Rv32Write for u128 {
-    fn write(&self, writer: fn(&[u8])) {
+    fn write_fmt(&self, f: &mut Formatter) {
+        let radix = u128::from(f.radix.clamp(2, 16));
         let mut num = *self;
 
         if num == 0 {
-            writer(b"0");
+            f.write_padded(1, |f| f.write_byte(b'0'));
             return;
         }
 
-        const MAX_DIGITS: usize = (u128::MAX.ilog10() + 1) as usize;
-
-        let num_digits = u32::from(num % 10 != 0) + num.ilog10();
+        const MAX_DIGITS: usize = u128::BITS as usize;
 
+        let num_digits = num.ilog(radix) + 1;
         let mut buf = [0u8; MAX_DIGITS];
 
         for i in 0..num_digits {
-            buf[(num_digits - i - 1) as usize] = b'0' + (num % 10) as u8;
-            num /= 10;
+            buf[(num_digits - i - 1) as usize] = LUT[(num % radix) as usize];
+            num /= radix;
         }
 
-        writer(&buf[0..num_digits as usize]);
+        let digits = &buf[0..num_digits as usize];
+        f.write_padded(digits.len(), |f| (f.writer)(digits));
     }
 }
 
 impl Rv32Write for i128 {
-    fn write(&self, writer: fn(&[u8])) {
+    fn write_fmt(&self, f: &mut Formatter) {
         let num = *self;
 
         if num < 0 {
-            writer(b"-");
+            f.write_byte(b'-');
+
+            // The sign above already took one column of `f.width`; pad the magnitude to what's
+            // left so the whole "-123" respects the requested field width, not just "123".
+            let width = f.width;
+            f.width = width.saturating_sub(1);
+            num.unsigned_abs().write_fmt(f);
+            f.width = width;
+        } else {
+            num.unsigned_abs().write_fmt(f);
         }
-
-        num.unsigned_abs().write(writer);
     }
 }
 
@@ -402,8 +828,8 @@ macro_rules! impl_write {
     ($parent:ty, [$($child:ty),+]) => {
         $(
         impl Rv32Write for $child {
-            fn write(&self, writer: fn(&[u8])) {
-                <$parent>::from(*self).write(writer)
+            fn write_fmt(&self, f: &mut Formatter) {
+                <$parent>::from(*self).write_fmt(f)
             }
         }
         )+
@@ -419,36 +845,49 @@ macro_rules! impl_binhex {
     ($($t:ty),+) => {
         $(
         impl Rv32Write for Hex<$t> {
-            fn write(&self, writer: fn(&[u8])) {
+            fn write_fmt(&self, f: &mut Formatter) {
+                const NIBBLES: u32 = <$t>::BITS / 4;
+                const SEPARATORS: u32 = NIBBLES.div_ceil(4);
+
                 let num = self.0;
+                let len = NIBBLES as usize + if f.separators { SEPARATORS as usize } else { 0 };
 
-                for i in 0..<$t>::BITS / 8 {
-                    if i % 4 == 0 {
-                        b'_'.write(writer);
-                    }
+                f.write_padded(len, |f| {
+                    for i in 0..NIBBLES {
+                        if f.separators && i % 4 == 0 {
+                            f.write_byte(b'_');
+                        }
 
-                    LUT[((num >> ((<$t>::BITS / 8 - i - 1)*4)) & 0xF) as usize].write(writer);
-                }
+                        let nibble = (num >> ((NIBBLES - i - 1) * 4)) & 0xF;
+                        f.write_byte(LUT[nibble as usize]);
+                    }
+                });
             }
         }
 
         impl Rv32Write for Binary<$t> {
-            fn write(&self, writer: fn(&[u8])) {
+            fn write_fmt(&self, f: &mut Formatter) {
+                const BITS: u32 = <$t>::BITS;
+                const SEPARATORS: u32 = BITS.div_ceil(4);
+
                 let num = self.0;
+                let len = BITS as usize + if f.separators { SEPARATORS as usize } else { 0 };
 
-                for i in 0..<$t>::BITS {
-                    if i % 4 == 0 {
-                        b'_'.write(writer);
-                    }
+                f.write_padded(len, |f| {
+                    for i in 0..BITS {
+                        if f.separators && i % 4 == 0 {
+                            f.write_byte(b'_');
+                        }
 
-                    let c = if (num >> (<$t>::BITS - i - 1)) & 1 == 1 {
-                        b'1'
-                    } else {
-                        b'0'
-                    };
+                        let bit = if (num >> (BITS - i - 1)) & 1 == 1 {
+                            b'1'
+                        } else {
+                            b'0'
+                        };
 
-                    c.write(writer);
-                }
+                        f.write_byte(bit);
+                    }
+                });
             }
         }
         )+
@@ -503,3 +942,21 @@ pub mod buffered_writer {
         self::flush(super::write_stderr);
     }
 }
+
+#[doc(hidden)]
+pub mod harness {
+    static mut CURRENT_TEST_FAILED: bool = false;
+
+    /// Mark the currently running test as failed. Called by the `assert!`/`assert_eq!`/
+    /// `assert_ne!` macros instead of aborting the whole run.
+    pub fn fail() {
+        unsafe { CURRENT_TEST_FAILED = true };
+    }
+
+    /// Read and reset the per-test failure flag. Called once after each test function returns.
+    pub fn take_failed() -> bool {
+        let failed = unsafe { CURRENT_TEST_FAILED };
+        unsafe { CURRENT_TEST_FAILED = false };
+        failed
+    }
+}